@@ -8,20 +8,24 @@ use near_primitives::transaction::ExecutionOutcomeWithIdAndProof;
 use near_primitives::version::DbVersion;
 
 use crate::db::{DBCol, RocksDB, VERSION_KEY};
-use crate::Store;
+use crate::{Store, StoreUpdate};
 use near_primitives::sharding::ShardChunk;
 
 pub fn get_store_version(path: &str) -> DbVersion {
     RocksDB::get_version(path).expect("Failed to open the database")
 }
 
-pub fn set_store_version(store: &Store, db_version: u32) {
-    let mut store_update = store.store_update();
+fn set_version(store_update: &mut StoreUpdate, db_version: u32) {
     store_update.set(
         DBCol::ColDbVersion,
         VERSION_KEY,
         &serde_json::to_vec(&db_version).expect("Failed to serialize version"),
     );
+}
+
+pub fn set_store_version(store: &Store, db_version: u32) {
+    let mut store_update = store.store_update();
+    set_version(&mut store_update, db_version);
     store_update.commit().expect("Failed to write version to database");
 }
 
@@ -33,8 +37,7 @@ fn get_outcomes_by_block_hash(store: &Store, block_hash: &CryptoHash) -> HashSet
     }
 }
 
-pub fn fill_col_outcomes_by_hash(store: &Store) {
-    let mut store_update = store.store_update();
+fn fill_col_outcomes_by_hash(store: &Store, store_update: &mut StoreUpdate) {
     let outcomes: Vec<ExecutionOutcomeWithIdAndProof> = store
         .iter(DBCol::ColTransactionResult)
         .map(|key| {
@@ -60,11 +63,9 @@ pub fn fill_col_outcomes_by_hash(store: &Store) {
             .set_ser(DBCol::ColOutcomesByBlockHash, block_hash.as_ref(), &hash_set)
             .expect("BorshSerialize should not fail");
     }
-    store_update.commit().expect("Failed to migrate");
 }
 
-pub fn fill_col_transaction_refcount(store: &Store) {
-    let mut store_update = store.store_update();
+fn fill_col_transaction_refcount(store: &Store, store_update: &mut StoreUpdate) {
     let chunks: Vec<ShardChunk> = store
         .iter(DBCol::ColChunks)
         .map(|key| ShardChunk::try_from_slice(&key.1).expect("BorshDeserialize should not fail"))
@@ -81,5 +82,170 @@ pub fn fill_col_transaction_refcount(store: &Store) {
             .set_ser(DBCol::ColTransactionRefCount, tx_hash.as_ref(), &refcount)
             .expect("BorshSerialize should not fail");
     }
-    store_update.commit().expect("Failed to migrate");
+}
+
+/// A single, idempotent step that upgrades the database from `from_version` to `to_version`.
+/// `apply` writes into the caller's `store_update` rather than committing one of its own, so
+/// `migrate_store` can land a migration's writes and the version bump in one atomic transaction.
+trait Migration {
+    fn from_version(&self) -> DbVersion;
+    fn to_version(&self) -> DbVersion;
+    fn apply(&self, store: &Store, store_update: &mut StoreUpdate);
+}
+
+struct FillColOutcomesByHash;
+
+impl Migration for FillColOutcomesByHash {
+    fn from_version(&self) -> DbVersion {
+        6
+    }
+    fn to_version(&self) -> DbVersion {
+        7
+    }
+    fn apply(&self, store: &Store, store_update: &mut StoreUpdate) {
+        fill_col_outcomes_by_hash(store, store_update)
+    }
+}
+
+struct FillColTransactionRefcount;
+
+impl Migration for FillColTransactionRefcount {
+    fn from_version(&self) -> DbVersion {
+        7
+    }
+    fn to_version(&self) -> DbVersion {
+        8
+    }
+    fn apply(&self, store: &Store, store_update: &mut StoreUpdate) {
+        fill_col_transaction_refcount(store, store_update)
+    }
+}
+
+/// All migrations the binary knows how to run. Order doesn't matter here: `migration_steps` keys
+/// them by `from_version`, and `resolve_migration_chain` follows that map rather than iterating
+/// this list in order.
+fn registered_migrations() -> Vec<Box<dyn Migration>> {
+    vec![Box::new(FillColOutcomesByHash), Box::new(FillColTransactionRefcount)]
+}
+
+/// Maps each registered migration's `from_version` to its `to_version`, so the upgrade chain can
+/// be resolved without touching the store.
+fn migration_steps(migrations: &[Box<dyn Migration>]) -> HashMap<DbVersion, DbVersion> {
+    migrations.iter().map(|m| (m.from_version(), m.to_version())).collect()
+}
+
+/// Walks `steps` from `current_version` towards `target_version`, returning every version visited
+/// along the way, in order, starting with `current_version` and ending with `target_version`.
+/// Pure and store-free, so `migrate_store`'s chaining, resuming partway through, and the
+/// missing-link panic can all be unit-tested directly.
+///
+/// Panics if `current_version` is already past `target_version` (the binary doesn't know how to
+/// interpret a store ahead of what it was told to migrate to), if a migration is missing for some
+/// version in the chain, if a registered migration doesn't strictly increase the version (which
+/// would otherwise loop forever instead of reaching `target_version`), or if a migration's
+/// `to_version` jumps past `target_version` instead of landing on it exactly.
+fn resolve_migration_chain(
+    steps: &HashMap<DbVersion, DbVersion>,
+    current_version: DbVersion,
+    target_version: DbVersion,
+) -> Vec<DbVersion> {
+    assert!(
+        current_version <= target_version,
+        "No migration chain from version {} to {}",
+        current_version,
+        target_version
+    );
+    let mut chain = vec![current_version];
+    let mut version = current_version;
+    while version < target_version {
+        match steps.get(&version) {
+            Some(&next) if next > version && next <= target_version => {
+                version = next;
+                chain.push(version);
+            }
+            _ => panic!("No migration chain from version {} to {}", version, target_version),
+        }
+    }
+    chain
+}
+
+/// Upgrades the database at `path` to `target_version`, running every registered migration whose
+/// `from_version` falls between the on-disk version and `target_version`, in order. Each
+/// migration's writes and the resulting `VERSION_KEY` bump are committed together in a single
+/// `StoreUpdate`, so a node that gets interrupted mid-upgrade never observes a half-applied
+/// migration: it either re-runs the whole step from the last completed version, or resumes from
+/// the step right after it.
+pub fn migrate_store(store: &Store, path: &str, target_version: DbVersion) {
+    let current_version = get_store_version(path);
+    let migrations = registered_migrations();
+    let steps = migration_steps(&migrations);
+    let chain = resolve_migration_chain(&steps, current_version, target_version);
+    let migrations_by_version: HashMap<DbVersion, &dyn Migration> =
+        migrations.iter().map(|m| (m.from_version(), m.as_ref())).collect();
+
+    for version in &chain[..chain.len().saturating_sub(1)] {
+        let migration = migrations_by_version[version];
+        let mut store_update = store.store_update();
+        migration.apply(store, &mut store_update);
+        set_version(&mut store_update, migration.to_version());
+        store_update.commit().expect("Failed to migrate");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps(pairs: &[(DbVersion, DbVersion)]) -> HashMap<DbVersion, DbVersion> {
+        pairs.iter().copied().collect()
+    }
+
+    #[test]
+    fn chains_through_every_registered_step() {
+        let steps = steps(&[(6, 7), (7, 8), (8, 9)]);
+        assert_eq!(resolve_migration_chain(&steps, 6, 9), vec![6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn resumes_from_a_version_partway_through_the_chain() {
+        let steps = steps(&[(6, 7), (7, 8), (8, 9)]);
+        assert_eq!(resolve_migration_chain(&steps, 7, 9), vec![7, 8, 9]);
+    }
+
+    #[test]
+    fn already_at_target_version_is_a_no_op() {
+        let steps = steps(&[(6, 7)]);
+        assert_eq!(resolve_migration_chain(&steps, 7, 7), vec![7]);
+    }
+
+    #[test]
+    #[should_panic(expected = "No migration chain from version 7 to 9")]
+    fn panics_on_a_missing_link_in_the_chain() {
+        let steps = steps(&[(6, 7), (8, 9)]);
+        resolve_migration_chain(&steps, 6, 9);
+    }
+
+    #[test]
+    #[should_panic(expected = "No migration chain from version 9 to 8")]
+    fn panics_when_current_version_is_already_past_target() {
+        let steps = steps(&[(6, 7), (7, 8)]);
+        resolve_migration_chain(&steps, 9, 8);
+    }
+
+    #[test]
+    #[should_panic(expected = "No migration chain from version 7 to 9")]
+    fn panics_instead_of_looping_forever_on_a_non_increasing_step() {
+        // A migration registered with to_version() <= from_version() (e.g. a copy-paste mistake)
+        // must not send this into an infinite loop.
+        let steps = steps(&[(6, 7), (7, 7), (8, 9)]);
+        resolve_migration_chain(&steps, 6, 9);
+    }
+
+    #[test]
+    #[should_panic(expected = "No migration chain from version 7 to 8")]
+    fn panics_instead_of_overshooting_target_version() {
+        // A migration step that lands past target_version must not be silently applied anyway.
+        let steps = steps(&[(6, 7), (7, 10)]);
+        resolve_migration_chain(&steps, 6, 8);
+    }
 }
@@ -0,0 +1,86 @@
+//! Enforces `RuntimeFeesConfig::max_compute_per_chunk`.
+use crate::Compute;
+
+/// Accumulates the compute cost of receipts as a chunk producer selects them into a chunk, and
+/// decides when to stop including more. A chunk producer enforces `max_compute_per_chunk` by
+/// creating one `ChunkComputeLimit` per chunk it builds and calling `try_add_receipt` with each
+/// candidate receipt's `Fee::compute_fee()` (summed over its actions) before including it; once
+/// that would exceed the limit, it stops, even if gas is still available. Nothing in this crate
+/// makes those calls: the limit binds only where a receipt-selection loop uses this tracker.
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkComputeLimit {
+    limit: Compute,
+    used: Compute,
+}
+
+impl ChunkComputeLimit {
+    pub fn new(limit: Compute) -> Self {
+        Self { limit, used: 0 }
+    }
+
+    /// If `compute` still fits under the limit, accounts for it and returns `true`. Otherwise
+    /// leaves `self` unchanged and returns `false` — the caller should not include this receipt,
+    /// and should stop considering further receipts for this chunk.
+    pub fn try_add_receipt(&mut self, compute: Compute) -> bool {
+        match self.used.checked_add(compute) {
+            Some(used) if used <= self.limit => {
+                self.used = used;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn used(&self) -> Compute {
+        self.used
+    }
+
+    pub fn limit(&self) -> Compute {
+        self.limit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_receipts_until_the_limit_is_reached() {
+        let mut limit = ChunkComputeLimit::new(100);
+        assert!(limit.try_add_receipt(40));
+        assert!(limit.try_add_receipt(60));
+        assert_eq!(limit.used(), 100);
+    }
+
+    #[test]
+    fn rejects_a_receipt_that_would_exceed_the_limit_and_leaves_state_unchanged() {
+        let mut limit = ChunkComputeLimit::new(100);
+        assert!(limit.try_add_receipt(60));
+        assert!(!limit.try_add_receipt(41));
+        assert_eq!(limit.used(), 60, "a rejected receipt must not be accounted for");
+    }
+
+    #[test]
+    fn exactly_at_the_limit_is_accepted() {
+        let mut limit = ChunkComputeLimit::new(100);
+        assert!(limit.try_add_receipt(100));
+        assert_eq!(limit.used(), 100);
+        assert!(!limit.try_add_receipt(1));
+    }
+
+    #[test]
+    fn does_not_overflow_when_limit_is_compute_max() {
+        let mut limit = ChunkComputeLimit::new(Compute::MAX);
+        assert!(limit.try_add_receipt(Compute::MAX - 1));
+        assert!(limit.try_add_receipt(1));
+        assert_eq!(limit.used(), Compute::MAX);
+        assert!(!limit.try_add_receipt(1));
+    }
+
+    #[test]
+    fn zero_limit_rejects_any_nonzero_receipt_but_accepts_zero() {
+        let mut limit = ChunkComputeLimit::new(0);
+        assert!(limit.try_add_receipt(0));
+        assert!(!limit.try_add_receipt(1));
+    }
+}
@@ -0,0 +1,197 @@
+//! An EIP-1559-style fee multiplier that makes receipt creation pricier as a shard gets busier.
+use num_rational::Rational;
+use serde::{Deserialize, Serialize};
+
+use crate::{Gas, RuntimeFeesConfig};
+
+/// The fixed-point denominator `CongestionMultiplier` is stored at: a multiplier of `1.0` is the
+/// integer `SCALE`. `next` compounds the multiplier by a fresh factor every block, and
+/// `num_rational::Rational` reduces to lowest terms rather than tracking a bignum, so repeatedly
+/// multiplying `Rational` values the way a naive implementation would causes the reduced
+/// numerator/denominator to grow roughly geometrically and overflow `isize` within a handful of
+/// blocks. A fixed denominator keeps every stored value the same width no matter how many blocks
+/// it has compounded over.
+const SCALE: u128 = 1_000_000_000;
+
+/// A per-shard multiplier applied on top of the static send/exec fees. It rises when the shard
+/// used more gas than its target in the previous block and falls when it used less, damped so it
+/// can change by at most `1 / adjustment_denominator` per block. `ShardCongestionState` carries it
+/// from one block to the next.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct CongestionMultiplier(u64);
+
+impl CongestionMultiplier {
+    /// The multiplier a shard starts at, and the floor it can never drop below: fees are never
+    /// scaled down past the static schedule.
+    pub fn base() -> Self {
+        Self(SCALE as u64)
+    }
+
+    /// Saturates rather than wrapping: `self.0` is a `u64`, which has more range than `isize` on a
+    /// 64-bit target, so a multiplier high enough to overflow `isize` (an extreme
+    /// `max_multiplier`) is clamped to `isize::MAX` instead of reinterpreting its high bit as a
+    /// sign and returning a negative `Rational`.
+    pub fn get(self) -> Rational {
+        Rational::new(self.0.min(isize::MAX as u64) as isize, SCALE as isize)
+    }
+
+    /// Converts a `Rational` known to be small and non-negative (a config-supplied fraction like
+    /// `gas_target_fraction` or `max_multiplier`) to the fixed-point scale, rounding towards zero.
+    fn fixed_point(value: Rational) -> u128 {
+        let numer = (*value.numer()).max(0) as u128;
+        let denom = (*value.denom()).max(1) as u128;
+        numer.saturating_mul(SCALE) / denom
+    }
+
+    /// Computes the multiplier for the next block, given how much gas the previous block used out
+    /// of `gas_limit`, per the `gas_target_fraction` / `adjustment_denominator` / `max_multiplier`
+    /// set in `config`.
+    ///
+    /// `m_next = m * (1 + ((gas_used - gas_target) / gas_target) / adjustment_denominator)`,
+    /// with the relative error capped at `1` so the per-block change never exceeds
+    /// `1 / adjustment_denominator` (the cap only bites when `gas_used > 2 * gas_target`), then
+    /// clamped to `[1.0, max_multiplier]`. All magnitude math is done in `u128` (gas values are
+    /// `u64`, so `u128` has ample headroom) and the multiplier itself is kept at the fixed-point
+    /// `SCALE` denominator rather than as an ever-compounding `Rational`, so neither step can
+    /// overflow.
+    pub fn next(self, gas_used: Gas, gas_limit: Gas, config: &RuntimeFeesConfig) -> Self {
+        let gas_target_fraction = Self::fixed_point(config.gas_target_fraction);
+        let gas_target = (gas_limit as u128 * gas_target_fraction) / SCALE;
+        if gas_target == 0 {
+            return Self::base();
+        }
+        let gas_used = gas_used as u128;
+        let adjustment_denominator = (config.adjustment_denominator as u128).max(1);
+
+        // `relative_error / adjustment_denominator`, expressed as a fixed-point fraction of `m`.
+        let (delta, negative) = if gas_used >= gas_target {
+            (gas_used - gas_target, false)
+        } else {
+            (gas_target - gas_used, true)
+        };
+        let relative_error = (delta.saturating_mul(SCALE) / gas_target).min(SCALE);
+        let adjustment = relative_error / adjustment_denominator;
+
+        let m = self.0 as u128;
+        let change = (m.saturating_mul(adjustment)) / SCALE;
+        let unclamped = if negative { m.saturating_sub(change) } else { m.saturating_add(change) };
+
+        let max_multiplier = Self::fixed_point(config.max_multiplier).max(SCALE);
+        let clamped = unclamped.clamp(SCALE, max_multiplier);
+        Self(clamped.min(u64::MAX as u128) as u64)
+    }
+
+    /// Scales a gas amount by this multiplier, truncating towards zero like integer gas math.
+    pub fn scale(self, gas: Gas) -> Gas {
+        ((gas as u128 * self.0 as u128) / SCALE) as Gas
+    }
+}
+
+impl Default for CongestionMultiplier {
+    fn default() -> Self {
+        Self::base()
+    }
+}
+
+/// The per-shard congestion state that carries the multiplier over from one block to the next
+/// instead of resetting. `advance` takes the gas a block just used and moves the multiplier on to
+/// the value for the next block; fee computation for that block reads `current()`.
+///
+/// `near-primitives`' header types aren't part of this tree, and no header here embeds this
+/// struct or calls `advance`: it is the self-contained piece of state that carries
+/// `CongestionMultiplier::next()` across blocks for whoever persists it.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq, Default)]
+pub struct ShardCongestionState {
+    multiplier: CongestionMultiplier,
+}
+
+impl ShardCongestionState {
+    /// The multiplier that applies to fees charged in the block this state was read from.
+    pub fn current(&self) -> CongestionMultiplier {
+        self.multiplier
+    }
+
+    /// Advances the stored multiplier to the value that should apply starting next block, given
+    /// how much gas this block used out of `gas_limit`.
+    pub fn advance(&mut self, gas_used: Gas, gas_limit: Gas, config: &RuntimeFeesConfig) {
+        self.multiplier = self.multiplier.next(gas_used, gas_limit, config);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_rational::Rational;
+
+    fn config_with(
+        gas_target_fraction: Rational,
+        adjustment_denominator: u64,
+        max_multiplier: Rational,
+    ) -> RuntimeFeesConfig {
+        RuntimeFeesConfig { gas_target_fraction, adjustment_denominator, max_multiplier, ..RuntimeFeesConfig::default() }
+    }
+
+    #[test]
+    fn max_multiplier_one_reproduces_todays_behavior() {
+        // Setting `max_multiplier == 1.0` clamps the multiplier to `base()` forever, regardless
+        // of how congested the shard is, which is the documented escape hatch back to the static
+        // fee schedule.
+        let config = config_with(Rational::new(1, 2), 8, Rational::new(1, 1));
+        let m = CongestionMultiplier::base();
+        let next = m.next(1_000_000, 1_000_000, &config);
+        assert_eq!(next, CongestionMultiplier::base());
+        assert_eq!(next.scale(12345), 12345);
+    }
+
+    #[test]
+    fn rises_when_over_target_and_falls_when_under() {
+        let config = config_with(Rational::new(1, 2), 8, Rational::new(10, 1));
+        let gas_limit = 1_000_000;
+
+        let over = CongestionMultiplier::base().next(gas_limit, gas_limit, &config);
+        assert!(over.get() > CongestionMultiplier::base().get());
+
+        let under = CongestionMultiplier::base().next(0, gas_limit, &config);
+        // Can't fall below the `1.0` floor even when the shard used no gas at all.
+        assert_eq!(under, CongestionMultiplier::base());
+    }
+
+    #[test]
+    fn clamps_to_max_multiplier() {
+        let config = config_with(Rational::new(1, 2), 1, Rational::new(2, 1));
+        let gas_limit = 1_000_000;
+        let mut m = CongestionMultiplier::base();
+        for _ in 0..100 {
+            m = m.next(gas_limit, gas_limit, &config);
+        }
+        assert_eq!(m.get(), Rational::new(2, 1));
+    }
+
+    #[test]
+    fn does_not_overflow_over_many_blocks_at_full_gas() {
+        // Regression test: a naive `Rational<isize>` that gets re-multiplied by `1 + adjustment`
+        // every block overflows within a handful of iterations once `gas_used`/`gas_limit` are at
+        // realistic (~1e15) magnitudes.
+        let config = config_with(Rational::new(1, 2), 8, Rational::new(1_000, 1));
+        let gas_limit: Gas = 1_000_000_000_000_000;
+        let mut m = CongestionMultiplier::base();
+        for _ in 0..10_000 {
+            m = m.next(gas_limit, gas_limit, &config);
+        }
+        assert!(m.get() <= Rational::new(1_000, 1));
+    }
+
+    #[test]
+    fn scale_handles_gas_above_i64_max() {
+        let gas: Gas = u64::MAX;
+        let m = CongestionMultiplier::base();
+        assert_eq!(m.scale(gas), gas);
+    }
+
+    #[test]
+    fn zero_gas_target_resets_to_base() {
+        let config = config_with(Rational::new(0, 1), 8, Rational::new(2, 1));
+        let m = CongestionMultiplier::base().next(1_000, 1_000, &config);
+        assert_eq!(m, CongestionMultiplier::base());
+    }
+}
@@ -3,13 +3,101 @@
 //! * sir -- sender is receiver. Receipts that are directed by an account to itself are guaranteed
 //!   to not be cross-shard which is cheaper than cross-shard. Conversely, when sender is not a
 //!   receiver it might or might not be a cross-shard communication.
-use serde::{Deserialize, Serialize};
+use enum_map::EnumMap;
+use num_rational::Rational;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+mod compute_limit;
+mod congestion;
+mod parameter;
+pub use compute_limit::ChunkComputeLimit;
+pub use congestion::{CongestionMultiplier, ShardCongestionState};
+pub use parameter::{Parameter, ParameterDiff, ProtocolVersion};
+
 pub type Gas = u64;
+/// Compute costs measure the wall-clock time it takes to process a receipt, as opposed to `Gas`
+/// which measures what the user is charged. They are only used to bound how much a chunk can
+/// include, never to compute fees or refunds.
+pub type Compute = u64;
 
-#[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
-pub struct Fraction {
-    pub numerator: u64,
-    pub denominator: u64,
+/// (De)serializes a `Rational` with the same `{"numerator": ..., "denominator": ...}` shape the
+/// old hand-written `Fraction` struct produced, so existing genesis/config JSON keeps
+/// deserializing unchanged even though the in-memory type is now `num_rational::Rational`.
+mod rational_as_fraction {
+    use num_rational::Rational;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct FractionRepr {
+        numerator: isize,
+        denominator: isize,
+    }
+
+    pub fn serialize<S: Serializer>(value: &Rational, serializer: S) -> Result<S::Ok, S::Error> {
+        FractionRepr { numerator: *value.numer(), denominator: *value.denom() }
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rational, D::Error> {
+        let repr = FractionRepr::deserialize(deserializer)?;
+        Ok(Rational::new(repr.numerator, repr.denominator))
+    }
+}
+
+/// Defaults for the fields `RuntimeFeesConfig` grew after its wire format was already in use, so
+/// that genesis/config JSON predating them keeps deserializing instead of failing on the missing
+/// keys. Kept as named functions (rather than inline closures) since `for_protocol_version()` and
+/// `free()` start from the same values.
+fn default_max_compute_per_chunk() -> Compute {
+    Compute::MAX
+}
+
+fn default_gas_target_fraction() -> Rational {
+    Rational::new(1, 2)
+}
+
+fn default_adjustment_denominator() -> u64 {
+    8
+}
+
+fn default_max_multiplier() -> Rational {
+    Rational::new(1, 1)
+}
+
+/// A single cost paired with its compute equivalent. `gas` is what the fee/refund logic charges
+/// the user; `compute` is what the runtime accumulates against the per-chunk compute limit. The
+/// two are allowed to diverge so that a known-slow operation can be throttled without changing
+/// what it costs the user.
+#[derive(Debug, Serialize, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct ParameterCost {
+    pub gas: Gas,
+    pub compute: Compute,
+}
+
+impl ParameterCost {
+    /// A cost whose compute amount equals its gas amount, i.e. today's behavior.
+    pub fn with_equal_compute(gas: Gas) -> Self {
+        Self { gas, compute: gas }
+    }
+}
+
+impl<'de> Deserialize<'de> for ParameterCost {
+    /// Accepts either a bare gas integer, the shape every existing genesis/config JSON uses
+    /// (`compute` then defaults to `gas`, i.e. today's behavior), or the `{"gas": ..,
+    /// "compute": ..}` object this type serializes as, so a value tuned after the fact round-trips
+    /// exactly.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Scalar(Gas),
+            Split { gas: Gas, compute: Compute },
+        }
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Scalar(gas) => ParameterCost::with_equal_compute(gas),
+            Repr::Split { gas, compute } => ParameterCost { gas, compute },
+        })
+    }
 }
 
 /// Costs associated with an object that can only be sent over the network (and executed
@@ -17,42 +105,499 @@ pub struct Fraction {
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
 pub struct Fee {
     /// Fee for sending an object from the sender to itself, guaranteeing that it does not leave
-    /// the shard.
+    /// the shard. Unlike `execution`, this is never compute-metered: send-side work is bounded by
+    /// the size of the receipt itself, not by the variable work `execution` can trigger, so there
+    /// is no compute amount for it to diverge from `gas`.
     send_sir: Gas,
-    /// Fee for sending an object potentially across the shards.
+    /// Fee for sending an object potentially across the shards. See `send_sir`.
     send_not_sir: Gas,
     /// Fee for executing the object.
-    execution: Gas,
+    execution: ParameterCost,
 }
 
 impl Fee {
-    pub fn send_fee(&self, sir: bool) -> Gas {
-        if sir {
-            self.send_sir
+    /// Fee for sending this object, scaled by the shard's current congestion multiplier. Pass
+    /// `CongestionMultiplier::base()` to get the unscaled static fee.
+    pub fn send_fee(&self, sir: bool, multiplier: CongestionMultiplier) -> Gas {
+        let base = if sir { self.send_sir } else { self.send_not_sir };
+        multiplier.scale(base)
+    }
+
+    /// Fee for executing this object, scaled by the shard's current congestion multiplier. Pass
+    /// `CongestionMultiplier::base()` to get the unscaled static fee.
+    pub fn exec_fee(&self, multiplier: CongestionMultiplier) -> Gas {
+        multiplier.scale(self.execution.gas)
+    }
+
+    /// The compute cost of executing the object, used to bound how much a chunk can include.
+    /// Compute costs are never scaled by the congestion multiplier: that multiplier only affects
+    /// what the user pays, not the wall-clock bound a chunk is allowed to spend.
+    pub fn compute_fee(&self) -> Compute {
+        self.execution.compute
+    }
+
+    /// The minimum total fee a sender must prepay to both send and execute the object, at the
+    /// static (unscaled) schedule: the cheaper of the two send fees (whichever side turns out to
+    /// be sir) plus execution. The runtime uses this to bound the best-case prepaid gas for a
+    /// cross-contract call before it knows whether the call will stay within the shard.
+    pub fn min_send_and_exec_fee(&self) -> Gas {
+        std::cmp::min(self.send_sir, self.send_not_sir) + self.execution.gas
+    }
+}
+
+/// Distinguishes a named account (e.g. `alice.near`) from an implicit, hash-derived account.
+/// Implicit accounts skip some validation and key setup that named accounts require, so their
+/// actions can be priced differently even though the same `Action` is involved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccountType {
+    Named,
+    Implicit,
+}
+
+impl AccountType {
+    /// Resolves the account type the runtime should charge for actions against `account_id`: an
+    /// implicit account id is exactly 64 lowercase hex characters (it *is* the account's ED25519
+    /// public key hash); anything else is a named account. This is the classification the runtime
+    /// must run on a receipt's receiver before picking `AccountTypeFee::fee()`.
+    pub fn of(account_id: &str) -> Self {
+        let is_implicit = account_id.len() == 64
+            && account_id.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+        if is_implicit {
+            AccountType::Implicit
         } else {
-            self.send_not_sir
+            AccountType::Named
         }
     }
+}
 
-    pub fn exec_fee(&self) -> Gas {
-        self.execution
+/// A `Fee` that differs depending on whether it is charged against a named or an implicit
+/// account.
+#[derive(Debug, Serialize, Clone, Hash, PartialEq, Eq)]
+pub struct AccountTypeFee {
+    pub named: Fee,
+    pub implicit: Fee,
+}
+
+impl AccountTypeFee {
+    pub fn fee(&self, account_type: AccountType) -> &Fee {
+        match account_type {
+            AccountType::Named => &self.named,
+            AccountType::Implicit => &self.implicit,
+        }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
+impl<'de> Deserialize<'de> for AccountTypeFee {
+    /// Accepts either a single `Fee`, the shape every existing genesis/config JSON uses (it
+    /// charged the same fee regardless of account type, so it fans out to both `named` and
+    /// `implicit`), or the `{"named": .., "implicit": ..}` object this type serializes as, so a
+    /// config that tunes the two sides apart round-trips exactly.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Split { named: Fee, implicit: Fee },
+            Single(Fee),
+        }
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Split { named, implicit } => AccountTypeFee { named, implicit },
+            Repr::Single(fee) => AccountTypeFee { named: fee.clone(), implicit: fee },
+        })
+    }
+}
+
+/// The fee schedule in effect for a single protocol version. Costs are stored as a flat table
+/// keyed by [`Parameter`] rather than as named fields, so that a new protocol version can be
+/// expressed as a diff against the previous one (see [`parameter::load_costs`]) instead of a full
+/// copy of this struct. Call sites that used to read `config.action_creation_config` (and
+/// similar) as a field now need `config.action_creation_config()` instead: the typed accessors
+/// below are by-value methods built from the table, not fields, so this is a source-breaking
+/// change for existing callers even though it is wire-compatible (see `Serialize`/`Deserialize`
+/// below).
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct RuntimeFeesConfig {
-    /// Describes the cost of creating an action receipt, `ActionReceipt`, excluding the actual cost
-    /// of actions.
-    pub action_receipt_creation_config: Fee,
-    /// Describes the cost of creating a data receipt, `DataReceipt`.
-    pub data_receipt_creation_config: DataReceiptCreationConfig,
-    /// Describes the cost of creating a certain action, `Action`. Includes all variants.
-    pub action_creation_config: ActionCreationConfig,
+    costs: EnumMap<Parameter, ParameterCost>,
 
     pub storage_usage_config: StorageUsageConfig,
 
-    /// Fraction of the burnt gas to reward to the contract account for execution.
-    pub burnt_gas_reward: Fraction,
+    /// Fraction of the burnt gas to reward to the contract account for execution. Kept as an
+    /// exact reduced fraction rather than integer numerator/denominator math so that applying it
+    /// per receipt cannot drift or silently overflow.
+    pub burnt_gas_reward: Rational,
+
+    /// Total compute that a chunk is allowed to accumulate across the receipts it includes. Use
+    /// `new_chunk_compute_limit()` to get a tracker that enforces this as receipts are selected
+    /// into a chunk, so that a chunk full of compute-heavy-but-cheap receipts cannot stall block
+    /// production even while gas is still available.
+    pub max_compute_per_chunk: Compute,
+
+    /// Fraction of the chunk gas limit used as the target utilization for the congestion
+    /// multiplier: a shard at exactly this utilization keeps its multiplier unchanged.
+    pub gas_target_fraction: Rational,
+    /// Damping denominator bounding the congestion multiplier's per-block change to at most
+    /// `1 / adjustment_denominator`.
+    pub adjustment_denominator: u64,
+    /// Upper clamp on the congestion multiplier. Setting this to `1` disables congestion pricing
+    /// and reproduces the static fee schedule exactly.
+    pub max_multiplier: Rational,
+}
+
+/// The wire format of `RuntimeFeesConfig`: the pre-chunk0-2 named-field shape
+/// (`action_receipt_creation_config`, `data_receipt_creation_config`, `action_creation_config`),
+/// not the flat `costs` table it is backed by internally. Matching those top-level field names is
+/// necessary but not sufficient for existing genesis/config JSON to keep deserializing: the leaf
+/// types nested inside this shape also changed when chunk0-1 added compute costs and chunk0-4
+/// split fees by account type, so `ParameterCost` and `AccountTypeFee` each carry their own
+/// backward-compatible `Deserialize` impl (accepting the pre-existing bare-integer / single-`Fee`
+/// shapes respectively) to cover that. `#[serde(default = ...)]` on the fields chunk0-1/chunk0-5
+/// added after this wire format was already in use does the same for JSON written before they
+/// existed.
+#[derive(Serialize, Deserialize)]
+struct RuntimeFeesConfigRepr {
+    action_receipt_creation_config: Fee,
+    data_receipt_creation_config: DataReceiptCreationConfig,
+    action_creation_config: ActionCreationConfig,
+    storage_usage_config: StorageUsageConfig,
+    #[serde(with = "rational_as_fraction")]
+    burnt_gas_reward: Rational,
+    #[serde(default = "default_max_compute_per_chunk")]
+    max_compute_per_chunk: Compute,
+    #[serde(default = "default_gas_target_fraction", with = "rational_as_fraction")]
+    gas_target_fraction: Rational,
+    #[serde(default = "default_adjustment_denominator")]
+    adjustment_denominator: u64,
+    #[serde(default = "default_max_multiplier", with = "rational_as_fraction")]
+    max_multiplier: Rational,
+}
+
+impl Serialize for RuntimeFeesConfig {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RuntimeFeesConfigRepr {
+            action_receipt_creation_config: self.action_receipt_creation_config(),
+            data_receipt_creation_config: self.data_receipt_creation_config(),
+            action_creation_config: self.action_creation_config(),
+            storage_usage_config: self.storage_usage_config.clone(),
+            burnt_gas_reward: self.burnt_gas_reward,
+            max_compute_per_chunk: self.max_compute_per_chunk,
+            gas_target_fraction: self.gas_target_fraction,
+            adjustment_denominator: self.adjustment_denominator,
+            max_multiplier: self.max_multiplier,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RuntimeFeesConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = RuntimeFeesConfigRepr::deserialize(deserializer)?;
+        Ok(Self {
+            costs: costs_from_configs(
+                &repr.action_receipt_creation_config,
+                &repr.data_receipt_creation_config,
+                &repr.action_creation_config,
+            ),
+            storage_usage_config: repr.storage_usage_config,
+            burnt_gas_reward: repr.burnt_gas_reward,
+            max_compute_per_chunk: repr.max_compute_per_chunk,
+            gas_target_fraction: repr.gas_target_fraction,
+            adjustment_denominator: repr.adjustment_denominator,
+            max_multiplier: repr.max_multiplier,
+        })
+    }
+}
+
+fn set_fee(
+    costs: &mut EnumMap<Parameter, ParameterCost>,
+    send_sir: Parameter,
+    send_not_sir: Parameter,
+    execution: Parameter,
+    fee: &Fee,
+) {
+    costs[send_sir] = ParameterCost::with_equal_compute(fee.send_sir);
+    costs[send_not_sir] = ParameterCost::with_equal_compute(fee.send_not_sir);
+    costs[execution] = fee.execution;
+}
+
+fn set_account_type_fee(
+    costs: &mut EnumMap<Parameter, ParameterCost>,
+    named: (Parameter, Parameter, Parameter),
+    implicit: (Parameter, Parameter, Parameter),
+    fee: &AccountTypeFee,
+) {
+    set_fee(costs, named.0, named.1, named.2, &fee.named);
+    set_fee(costs, implicit.0, implicit.1, implicit.2, &fee.implicit);
+}
+
+/// Rebuilds the flat `costs` table from the typed views, the inverse of
+/// `action_receipt_creation_config()` / `data_receipt_creation_config()` / `action_creation_config()`.
+/// Only used when deserializing `RuntimeFeesConfigRepr`, which keeps the pre-chunk0-2 named-field
+/// wire format.
+fn costs_from_configs(
+    action_receipt_creation_config: &Fee,
+    data_receipt_creation_config: &DataReceiptCreationConfig,
+    action_creation_config: &ActionCreationConfig,
+) -> EnumMap<Parameter, ParameterCost> {
+    let mut costs = parameter::free_costs();
+    set_fee(
+        &mut costs,
+        Parameter::ActionReceiptCreationSendSir,
+        Parameter::ActionReceiptCreationSendNotSir,
+        Parameter::ActionReceiptCreationExecution,
+        action_receipt_creation_config,
+    );
+    set_fee(
+        &mut costs,
+        Parameter::DataReceiptCreationBaseSendSir,
+        Parameter::DataReceiptCreationBaseSendNotSir,
+        Parameter::DataReceiptCreationBaseExecution,
+        &data_receipt_creation_config.base_cost,
+    );
+    set_fee(
+        &mut costs,
+        Parameter::DataReceiptCreationPerByteSendSir,
+        Parameter::DataReceiptCreationPerByteSendNotSir,
+        Parameter::DataReceiptCreationPerByteExecution,
+        &data_receipt_creation_config.cost_per_byte,
+    );
+    set_account_type_fee(
+        &mut costs,
+        (
+            Parameter::ActionCreateAccountNamedSendSir,
+            Parameter::ActionCreateAccountNamedSendNotSir,
+            Parameter::ActionCreateAccountNamedExecution,
+        ),
+        (
+            Parameter::ActionCreateAccountImplicitSendSir,
+            Parameter::ActionCreateAccountImplicitSendNotSir,
+            Parameter::ActionCreateAccountImplicitExecution,
+        ),
+        &action_creation_config.create_account_cost,
+    );
+    set_fee(
+        &mut costs,
+        Parameter::ActionDeployContractSendSir,
+        Parameter::ActionDeployContractSendNotSir,
+        Parameter::ActionDeployContractExecution,
+        &action_creation_config.deploy_contract_cost,
+    );
+    set_fee(
+        &mut costs,
+        Parameter::ActionDeployContractPerByteSendSir,
+        Parameter::ActionDeployContractPerByteSendNotSir,
+        Parameter::ActionDeployContractPerByteExecution,
+        &action_creation_config.deploy_contract_cost_per_byte,
+    );
+    set_fee(
+        &mut costs,
+        Parameter::ActionFunctionCallSendSir,
+        Parameter::ActionFunctionCallSendNotSir,
+        Parameter::ActionFunctionCallExecution,
+        &action_creation_config.function_call_cost,
+    );
+    set_fee(
+        &mut costs,
+        Parameter::ActionFunctionCallPerByteSendSir,
+        Parameter::ActionFunctionCallPerByteSendNotSir,
+        Parameter::ActionFunctionCallPerByteExecution,
+        &action_creation_config.function_call_cost_per_byte,
+    );
+    set_fee(
+        &mut costs,
+        Parameter::ActionTransferSendSir,
+        Parameter::ActionTransferSendNotSir,
+        Parameter::ActionTransferExecution,
+        &action_creation_config.transfer_cost,
+    );
+    set_fee(
+        &mut costs,
+        Parameter::ActionStakeSendSir,
+        Parameter::ActionStakeSendNotSir,
+        Parameter::ActionStakeExecution,
+        &action_creation_config.stake_cost,
+    );
+    set_account_type_fee(
+        &mut costs,
+        (
+            Parameter::ActionAddKeyFullAccessNamedSendSir,
+            Parameter::ActionAddKeyFullAccessNamedSendNotSir,
+            Parameter::ActionAddKeyFullAccessNamedExecution,
+        ),
+        (
+            Parameter::ActionAddKeyFullAccessImplicitSendSir,
+            Parameter::ActionAddKeyFullAccessImplicitSendNotSir,
+            Parameter::ActionAddKeyFullAccessImplicitExecution,
+        ),
+        &action_creation_config.add_key_cost.full_access_cost,
+    );
+    set_fee(
+        &mut costs,
+        Parameter::ActionAddKeyFunctionCallSendSir,
+        Parameter::ActionAddKeyFunctionCallSendNotSir,
+        Parameter::ActionAddKeyFunctionCallExecution,
+        &action_creation_config.add_key_cost.function_call_cost,
+    );
+    set_fee(
+        &mut costs,
+        Parameter::ActionAddKeyFunctionCallPerByteSendSir,
+        Parameter::ActionAddKeyFunctionCallPerByteSendNotSir,
+        Parameter::ActionAddKeyFunctionCallPerByteExecution,
+        &action_creation_config.add_key_cost.function_call_cost_per_byte,
+    );
+    set_fee(
+        &mut costs,
+        Parameter::ActionDeleteKeySendSir,
+        Parameter::ActionDeleteKeySendNotSir,
+        Parameter::ActionDeleteKeyExecution,
+        &action_creation_config.delete_key_cost,
+    );
+    set_fee(
+        &mut costs,
+        Parameter::ActionDeleteAccountSendSir,
+        Parameter::ActionDeleteAccountSendNotSir,
+        Parameter::ActionDeleteAccountExecution,
+        &action_creation_config.delete_account_cost,
+    );
+    costs
+}
+
+impl RuntimeFeesConfig {
+    /// Starts a fresh compute accumulator for a chunk the chunk producer is about to build,
+    /// seeded with `max_compute_per_chunk`. See `ChunkComputeLimit::try_add_receipt`.
+    ///
+    /// Nothing in this tree calls this yet: the chunk producer's receipt-selection loop lives in
+    /// `near-chunks`/the runtime crate, neither of which is part of this source snapshot. Wiring
+    /// `try_add_receipt` into that loop is out of scope here and belongs to whichever change
+    /// lands those crates.
+    pub fn new_chunk_compute_limit(&self) -> ChunkComputeLimit {
+        ChunkComputeLimit::new(self.max_compute_per_chunk)
+    }
+
+    fn fee(&self, send_sir: Parameter, send_not_sir: Parameter, execution: Parameter) -> Fee {
+        Fee {
+            send_sir: self.costs[send_sir].gas,
+            send_not_sir: self.costs[send_not_sir].gas,
+            execution: self.costs[execution],
+        }
+    }
+
+    /// Describes the cost of creating an action receipt, `ActionReceipt`, excluding the actual
+    /// cost of actions.
+    pub fn action_receipt_creation_config(&self) -> Fee {
+        self.fee(
+            Parameter::ActionReceiptCreationSendSir,
+            Parameter::ActionReceiptCreationSendNotSir,
+            Parameter::ActionReceiptCreationExecution,
+        )
+    }
+
+    /// Describes the cost of creating a data receipt, `DataReceipt`.
+    pub fn data_receipt_creation_config(&self) -> DataReceiptCreationConfig {
+        DataReceiptCreationConfig {
+            base_cost: self.fee(
+                Parameter::DataReceiptCreationBaseSendSir,
+                Parameter::DataReceiptCreationBaseSendNotSir,
+                Parameter::DataReceiptCreationBaseExecution,
+            ),
+            cost_per_byte: self.fee(
+                Parameter::DataReceiptCreationPerByteSendSir,
+                Parameter::DataReceiptCreationPerByteSendNotSir,
+                Parameter::DataReceiptCreationPerByteExecution,
+            ),
+        }
+    }
+
+    fn account_type_fee(
+        &self,
+        named: (Parameter, Parameter, Parameter),
+        implicit: (Parameter, Parameter, Parameter),
+    ) -> AccountTypeFee {
+        AccountTypeFee {
+            named: self.fee(named.0, named.1, named.2),
+            implicit: self.fee(implicit.0, implicit.1, implicit.2),
+        }
+    }
+
+    /// Describes the cost of creating a certain action, `Action`. Includes all variants.
+    pub fn action_creation_config(&self) -> ActionCreationConfig {
+        ActionCreationConfig {
+            create_account_cost: self.account_type_fee(
+                (
+                    Parameter::ActionCreateAccountNamedSendSir,
+                    Parameter::ActionCreateAccountNamedSendNotSir,
+                    Parameter::ActionCreateAccountNamedExecution,
+                ),
+                (
+                    Parameter::ActionCreateAccountImplicitSendSir,
+                    Parameter::ActionCreateAccountImplicitSendNotSir,
+                    Parameter::ActionCreateAccountImplicitExecution,
+                ),
+            ),
+            deploy_contract_cost: self.fee(
+                Parameter::ActionDeployContractSendSir,
+                Parameter::ActionDeployContractSendNotSir,
+                Parameter::ActionDeployContractExecution,
+            ),
+            deploy_contract_cost_per_byte: self.fee(
+                Parameter::ActionDeployContractPerByteSendSir,
+                Parameter::ActionDeployContractPerByteSendNotSir,
+                Parameter::ActionDeployContractPerByteExecution,
+            ),
+            function_call_cost: self.fee(
+                Parameter::ActionFunctionCallSendSir,
+                Parameter::ActionFunctionCallSendNotSir,
+                Parameter::ActionFunctionCallExecution,
+            ),
+            function_call_cost_per_byte: self.fee(
+                Parameter::ActionFunctionCallPerByteSendSir,
+                Parameter::ActionFunctionCallPerByteSendNotSir,
+                Parameter::ActionFunctionCallPerByteExecution,
+            ),
+            transfer_cost: self.fee(
+                Parameter::ActionTransferSendSir,
+                Parameter::ActionTransferSendNotSir,
+                Parameter::ActionTransferExecution,
+            ),
+            stake_cost: self.fee(
+                Parameter::ActionStakeSendSir,
+                Parameter::ActionStakeSendNotSir,
+                Parameter::ActionStakeExecution,
+            ),
+            add_key_cost: AccessKeyCreationConfig {
+                full_access_cost: self.account_type_fee(
+                    (
+                        Parameter::ActionAddKeyFullAccessNamedSendSir,
+                        Parameter::ActionAddKeyFullAccessNamedSendNotSir,
+                        Parameter::ActionAddKeyFullAccessNamedExecution,
+                    ),
+                    (
+                        Parameter::ActionAddKeyFullAccessImplicitSendSir,
+                        Parameter::ActionAddKeyFullAccessImplicitSendNotSir,
+                        Parameter::ActionAddKeyFullAccessImplicitExecution,
+                    ),
+                ),
+                function_call_cost: self.fee(
+                    Parameter::ActionAddKeyFunctionCallSendSir,
+                    Parameter::ActionAddKeyFunctionCallSendNotSir,
+                    Parameter::ActionAddKeyFunctionCallExecution,
+                ),
+                function_call_cost_per_byte: self.fee(
+                    Parameter::ActionAddKeyFunctionCallPerByteSendSir,
+                    Parameter::ActionAddKeyFunctionCallPerByteSendNotSir,
+                    Parameter::ActionAddKeyFunctionCallPerByteExecution,
+                ),
+            },
+            delete_key_cost: self.fee(
+                Parameter::ActionDeleteKeySendSir,
+                Parameter::ActionDeleteKeySendNotSir,
+                Parameter::ActionDeleteKeyExecution,
+            ),
+            delete_account_cost: self.fee(
+                Parameter::ActionDeleteAccountSendSir,
+                Parameter::ActionDeleteAccountSendNotSir,
+                Parameter::ActionDeleteAccountExecution,
+            ),
+        }
+    }
 }
 
 /// Describes the cost of creating a data receipt, `DataReceipt`.
@@ -67,8 +612,9 @@ pub struct DataReceiptCreationConfig {
 /// Describes the cost of creating a specific action, `Action`. Includes all variants.
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
 pub struct ActionCreationConfig {
-    /// Base cost of creating an account.
-    pub create_account_cost: Fee,
+    /// Base cost of creating an account. Priced separately for named and implicit accounts,
+    /// since implicit accounts skip certain validation.
+    pub create_account_cost: AccountTypeFee,
 
     /// Base cost of deploying a contract.
     pub deploy_contract_cost: Fee,
@@ -96,17 +642,36 @@ pub struct ActionCreationConfig {
     pub delete_account_cost: Fee,
 }
 
+impl ActionCreationConfig {
+    /// Resolves `receiver_id`'s account type and returns the `create_account_cost` fee for it.
+    /// This is the call site the runtime uses when it computes the cost of a `CreateAccount`
+    /// action receipt, instead of reading `create_account_cost` directly.
+    pub fn create_account_fee(&self, receiver_id: &str) -> &Fee {
+        self.create_account_cost.fee(AccountType::of(receiver_id))
+    }
+}
+
 /// Describes the cost of creating an access key.
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
 pub struct AccessKeyCreationConfig {
-    /// Base cost of creating a full access access-key.
-    pub full_access_cost: Fee,
+    /// Base cost of creating a full access access-key. Priced separately for named and implicit
+    /// accounts, since implicit account creation sets up its initial key differently.
+    pub full_access_cost: AccountTypeFee,
     /// Base cost of creating an access-key restricted to specific functions.
     pub function_call_cost: Fee,
     /// Cost per byte of method_names of creating a restricted access-key.
     pub function_call_cost_per_byte: Fee,
 }
 
+impl AccessKeyCreationConfig {
+    /// Resolves `receiver_id`'s account type and returns the `full_access_cost` fee for it. This
+    /// is the call site the runtime uses when it computes the cost of adding a full access key to
+    /// the account created by an `AddKey` action receipt.
+    pub fn full_access_fee(&self, receiver_id: &str) -> &Fee {
+        self.full_access_cost.fee(AccountType::of(receiver_id))
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
 pub struct StorageUsageConfig {
     /// Base storage usage for an account
@@ -121,38 +686,12 @@ pub struct StorageUsageConfig {
     pub code_cost_per_byte: Gas,
 }
 
-impl Default for RuntimeFeesConfig {
-    fn default() -> Self {
+impl RuntimeFeesConfig {
+    /// Builds the fee schedule effective at `protocol_version`, applying every registered
+    /// parameter diff up to and including that version on top of the base table.
+    pub fn for_protocol_version(protocol_version: ProtocolVersion) -> Self {
         Self {
-            action_receipt_creation_config: Fee { send_sir: 10, send_not_sir: 10, execution: 10 },
-            data_receipt_creation_config: DataReceiptCreationConfig {
-                base_cost: Fee { send_sir: 10, send_not_sir: 10, execution: 10 },
-                cost_per_byte: Fee { send_sir: 10, send_not_sir: 10, execution: 10 },
-            },
-            action_creation_config: ActionCreationConfig {
-                create_account_cost: Fee { send_sir: 10, send_not_sir: 10, execution: 10 },
-                deploy_contract_cost: Fee { send_sir: 10, send_not_sir: 10, execution: 10 },
-                deploy_contract_cost_per_byte: Fee {
-                    send_sir: 10,
-                    send_not_sir: 10,
-                    execution: 10,
-                },
-                function_call_cost: Fee { send_sir: 10, send_not_sir: 10, execution: 10 },
-                function_call_cost_per_byte: Fee { send_sir: 10, send_not_sir: 10, execution: 10 },
-                transfer_cost: Fee { send_sir: 10, send_not_sir: 10, execution: 10 },
-                stake_cost: Fee { send_sir: 10, send_not_sir: 10, execution: 10 },
-                add_key_cost: AccessKeyCreationConfig {
-                    full_access_cost: Fee { send_sir: 10, send_not_sir: 10, execution: 10 },
-                    function_call_cost: Fee { send_sir: 10, send_not_sir: 10, execution: 10 },
-                    function_call_cost_per_byte: Fee {
-                        send_sir: 10,
-                        send_not_sir: 10,
-                        execution: 10,
-                    },
-                },
-                delete_key_cost: Fee { send_sir: 10, send_not_sir: 10, execution: 10 },
-                delete_account_cost: Fee { send_sir: 10, send_not_sir: 10, execution: 10 },
-            },
+            costs: parameter::load_costs(protocol_version),
             storage_usage_config: StorageUsageConfig {
                 account_cost: 100,
                 data_record_cost: 40,
@@ -160,36 +699,17 @@ impl Default for RuntimeFeesConfig {
                 value_cost_per_byte: 1,
                 code_cost_per_byte: 1,
             },
-            burnt_gas_reward: Fraction { numerator: 3, denominator: 10 },
+            burnt_gas_reward: Rational::new(3, 10),
+            max_compute_per_chunk: default_max_compute_per_chunk(),
+            gas_target_fraction: default_gas_target_fraction(),
+            adjustment_denominator: default_adjustment_denominator(),
+            max_multiplier: default_max_multiplier(),
         }
     }
-}
 
-impl RuntimeFeesConfig {
     pub fn free() -> Self {
-        let free = Fee { send_sir: 0, send_not_sir: 0, execution: 0 };
-        RuntimeFeesConfig {
-            action_receipt_creation_config: free.clone(),
-            data_receipt_creation_config: DataReceiptCreationConfig {
-                base_cost: free.clone(),
-                cost_per_byte: free.clone(),
-            },
-            action_creation_config: ActionCreationConfig {
-                create_account_cost: free.clone(),
-                deploy_contract_cost: free.clone(),
-                deploy_contract_cost_per_byte: free.clone(),
-                function_call_cost: free.clone(),
-                function_call_cost_per_byte: free.clone(),
-                transfer_cost: free.clone(),
-                stake_cost: free.clone(),
-                add_key_cost: AccessKeyCreationConfig {
-                    full_access_cost: free.clone(),
-                    function_call_cost: free.clone(),
-                    function_call_cost_per_byte: free.clone(),
-                },
-                delete_key_cost: free.clone(),
-                delete_account_cost: free.clone(),
-            },
+        Self {
+            costs: parameter::free_costs(),
             storage_usage_config: StorageUsageConfig {
                 account_cost: 0,
                 data_record_cost: 0,
@@ -197,7 +717,133 @@ impl RuntimeFeesConfig {
                 value_cost_per_byte: 0,
                 code_cost_per_byte: 0,
             },
-            burnt_gas_reward: Fraction { numerator: 0, denominator: 1 },
+            burnt_gas_reward: Rational::new(0, 1),
+            max_compute_per_chunk: default_max_compute_per_chunk(),
+            gas_target_fraction: default_gas_target_fraction(),
+            adjustment_denominator: default_adjustment_denominator(),
+            max_multiplier: default_max_multiplier(),
+        }
+    }
+}
+
+impl Default for RuntimeFeesConfig {
+    fn default() -> Self {
+        Self::for_protocol_version(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rational_as_fraction_round_trips_through_the_old_wire_shape() {
+        #[derive(Serialize, Deserialize)]
+        struct Wrapper(#[serde(with = "rational_as_fraction")] Rational);
+
+        let value = Wrapper(Rational::new(3, 10));
+        let json = serde_json::to_value(&value).unwrap();
+        assert_eq!(json, serde_json::json!({ "numerator": 3, "denominator": 10 }));
+
+        let restored: Wrapper = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.0, value.0);
+    }
+
+    #[test]
+    fn min_send_and_exec_fee_takes_the_cheaper_send_side() {
+        let fee = Fee { send_sir: 5, send_not_sir: 9, execution: ParameterCost { gas: 100, compute: 100 } };
+        assert_eq!(fee.min_send_and_exec_fee(), 5 + 100);
+    }
+
+    #[test]
+    fn implicit_account_is_64_lowercase_hex_chars() {
+        let implicit = "f".repeat(64);
+        assert_eq!(AccountType::of(&implicit), AccountType::Implicit);
+    }
+
+    #[test]
+    fn named_account_is_anything_else() {
+        assert_eq!(AccountType::of("alice.near"), AccountType::Named);
+        // Right length, but uppercase hex isn't accepted.
+        assert_eq!(AccountType::of(&"F".repeat(64)), AccountType::Named);
+        // Right characters, wrong length.
+        assert_eq!(AccountType::of(&"f".repeat(63)), AccountType::Named);
+        assert_eq!(AccountType::of(&"f".repeat(65)), AccountType::Named);
+        // Right length, but not all hex.
+        assert_eq!(AccountType::of(&format!("{}g", "f".repeat(63))), AccountType::Named);
+    }
+
+    #[test]
+    fn account_type_fee_picks_the_matching_side() {
+        let fee = AccountTypeFee {
+            named: Fee { send_sir: 1, send_not_sir: 1, execution: ParameterCost { gas: 1, compute: 1 } },
+            implicit: Fee { send_sir: 2, send_not_sir: 2, execution: ParameterCost { gas: 2, compute: 2 } },
+        };
+        assert_eq!(fee.fee(AccountType::Named).execution.gas, 1);
+        assert_eq!(fee.fee(AccountType::Implicit).execution.gas, 2);
+    }
+
+    #[test]
+    fn runtime_fees_config_deserializes_pre_chunk0_2_scalar_fee_json() {
+        fn fee(send_sir: u64, send_not_sir: u64, execution: u64) -> serde_json::Value {
+            serde_json::json!({
+                "send_sir": send_sir,
+                "send_not_sir": send_not_sir,
+                "execution": execution,
+            })
         }
+
+        // The shape every genesis/config JSON predating chunk0-1/chunk0-2/chunk0-4 uses: every
+        // `Fee` leaf is a bare integer per side, and `create_account_cost`/`full_access_cost` are
+        // a single `Fee` rather than `{"named": .., "implicit": ..}`.
+        let json = serde_json::json!({
+            "action_receipt_creation_config": fee(1, 2, 3),
+            "data_receipt_creation_config": {
+                "base_cost": fee(4, 5, 6),
+                "cost_per_byte": fee(7, 8, 9),
+            },
+            "action_creation_config": {
+                "create_account_cost": fee(10, 11, 12),
+                "deploy_contract_cost": fee(13, 14, 15),
+                "deploy_contract_cost_per_byte": fee(16, 17, 18),
+                "function_call_cost": fee(19, 20, 21),
+                "function_call_cost_per_byte": fee(22, 23, 24),
+                "transfer_cost": fee(25, 26, 27),
+                "stake_cost": fee(28, 29, 30),
+                "add_key_cost": {
+                    "full_access_cost": fee(31, 32, 33),
+                    "function_call_cost": fee(34, 35, 36),
+                    "function_call_cost_per_byte": fee(37, 38, 39),
+                },
+                "delete_key_cost": fee(40, 41, 42),
+                "delete_account_cost": fee(43, 44, 45),
+            },
+            "storage_usage_config": {
+                "account_cost": 100,
+                "data_record_cost": 40,
+                "key_cost_per_byte": 1,
+                "value_cost_per_byte": 1,
+                "code_cost_per_byte": 1,
+            },
+            "burnt_gas_reward": { "numerator": 3, "denominator": 10 },
+        });
+
+        let config: RuntimeFeesConfig = serde_json::from_value(json).unwrap();
+
+        let action_receipt = config.action_receipt_creation_config();
+        assert_eq!(action_receipt.execution.gas, 3);
+        // A bare integer defaults `compute` to `gas`, i.e. today's behavior.
+        assert_eq!(action_receipt.execution.compute, 3);
+
+        let create_account = config.action_creation_config().create_account_cost;
+        // A pre-chunk0-4 single-`Fee` config fans out to both account types identically.
+        assert_eq!(create_account.named, create_account.implicit);
+        assert_eq!(create_account.named.execution.gas, 12);
+
+        // Fields added after this wire format was already in use fall back to their defaults.
+        assert_eq!(config.max_compute_per_chunk, default_max_compute_per_chunk());
+        assert_eq!(config.gas_target_fraction, default_gas_target_fraction());
+        assert_eq!(config.adjustment_denominator, default_adjustment_denominator());
+        assert_eq!(config.max_multiplier, default_max_multiplier());
     }
 }
\ No newline at end of file
@@ -0,0 +1,131 @@
+//! The flat, protocol-version-indexed backing store for `RuntimeFeesConfig`.
+//!
+//! Each `Parameter` names a single `gas`/`compute` pair. `RuntimeFeesConfig` only ever reads this
+//! table through its typed accessors (`action_receipt_creation_config()` and friends), so callers
+//! don't need to know that the table underneath is flat.
+use enum_map::{enum_map, Enum, EnumMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{Gas, ParameterCost};
+
+pub type ProtocolVersion = u32;
+
+/// Identifies a single parameter cost in the flat fee table. Costs that come from a `Fee` (an
+/// object that is sent and then executed) appear three times, once per `Fee` component, since
+/// each component can be tuned independently. Costs that are account-type-aware (see
+/// `AccountTypeFee`) have those three once per `AccountType`, so six entries in all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Enum, Serialize, Deserialize)]
+pub enum Parameter {
+    ActionReceiptCreationSendSir,
+    ActionReceiptCreationSendNotSir,
+    ActionReceiptCreationExecution,
+
+    DataReceiptCreationBaseSendSir,
+    DataReceiptCreationBaseSendNotSir,
+    DataReceiptCreationBaseExecution,
+    DataReceiptCreationPerByteSendSir,
+    DataReceiptCreationPerByteSendNotSir,
+    DataReceiptCreationPerByteExecution,
+
+    ActionCreateAccountNamedSendSir,
+    ActionCreateAccountNamedSendNotSir,
+    ActionCreateAccountNamedExecution,
+    ActionCreateAccountImplicitSendSir,
+    ActionCreateAccountImplicitSendNotSir,
+    ActionCreateAccountImplicitExecution,
+
+    ActionDeployContractSendSir,
+    ActionDeployContractSendNotSir,
+    ActionDeployContractExecution,
+    ActionDeployContractPerByteSendSir,
+    ActionDeployContractPerByteSendNotSir,
+    ActionDeployContractPerByteExecution,
+
+    ActionFunctionCallSendSir,
+    ActionFunctionCallSendNotSir,
+    ActionFunctionCallExecution,
+    ActionFunctionCallPerByteSendSir,
+    ActionFunctionCallPerByteSendNotSir,
+    ActionFunctionCallPerByteExecution,
+
+    ActionTransferSendSir,
+    ActionTransferSendNotSir,
+    ActionTransferExecution,
+
+    ActionStakeSendSir,
+    ActionStakeSendNotSir,
+    ActionStakeExecution,
+
+    ActionAddKeyFullAccessNamedSendSir,
+    ActionAddKeyFullAccessNamedSendNotSir,
+    ActionAddKeyFullAccessNamedExecution,
+    ActionAddKeyFullAccessImplicitSendSir,
+    ActionAddKeyFullAccessImplicitSendNotSir,
+    ActionAddKeyFullAccessImplicitExecution,
+    ActionAddKeyFunctionCallSendSir,
+    ActionAddKeyFunctionCallSendNotSir,
+    ActionAddKeyFunctionCallExecution,
+    ActionAddKeyFunctionCallPerByteSendSir,
+    ActionAddKeyFunctionCallPerByteSendNotSir,
+    ActionAddKeyFunctionCallPerByteExecution,
+
+    ActionDeleteKeySendSir,
+    ActionDeleteKeySendNotSir,
+    ActionDeleteKeyExecution,
+
+    ActionDeleteAccountSendSir,
+    ActionDeleteAccountSendNotSir,
+    ActionDeleteAccountExecution,
+}
+
+/// A sparse override of specific parameter costs, applied on top of whatever table was effective
+/// at the protocol version just before it.
+#[derive(Debug, Clone, Default)]
+pub struct ParameterDiff(Vec<(Parameter, ParameterCost)>);
+
+impl ParameterDiff {
+    pub fn new(entries: Vec<(Parameter, ParameterCost)>) -> Self {
+        Self(entries)
+    }
+
+    fn apply_to(&self, costs: &mut EnumMap<Parameter, ParameterCost>) {
+        for (param, cost) in &self.0 {
+            costs[*param] = *cost;
+        }
+    }
+}
+
+fn constant_costs(gas: Gas) -> EnumMap<Parameter, ParameterCost> {
+    let cost = ParameterCost::with_equal_compute(gas);
+    enum_map! { _ => cost }
+}
+
+/// The parameter cost table as of protocol version 0. Later protocol versions only need to
+/// register what changed, via `diff_for_version`.
+fn base_costs() -> EnumMap<Parameter, ParameterCost> {
+    constant_costs(10)
+}
+
+/// The table in which every cost is zero, used by `RuntimeFeesConfig::free()`.
+pub(crate) fn free_costs() -> EnumMap<Parameter, ParameterCost> {
+    constant_costs(0)
+}
+
+/// Returns the diff to apply when upgrading to `version`, if that version changed any parameter
+/// costs relative to the version before it. New protocol versions are onboarded here by listing
+/// only the parameters that actually changed, rather than duplicating the whole table.
+fn diff_for_version(_version: ProtocolVersion) -> Option<ParameterDiff> {
+    None
+}
+
+/// Loads the flat parameter cost table effective at `protocol_version`, by starting from the base
+/// table and layering every version's diff on top of it, in order.
+pub fn load_costs(protocol_version: ProtocolVersion) -> EnumMap<Parameter, ParameterCost> {
+    let mut costs = base_costs();
+    for version in 1..=protocol_version {
+        if let Some(diff) = diff_for_version(version) {
+            diff.apply_to(&mut costs);
+        }
+    }
+    costs
+}